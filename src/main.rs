@@ -1,66 +1,388 @@
 use std::f32::consts::PI;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
 
+use bevy::audio::{AudioSink, AudioSinkPlayback};
 use bevy::prelude::*;
-use rand::Rng;
+use bevy_ggrs::{GGRSPlugin, GGRSSchedule, PlayerInputs, Rollback, RollbackIdProvider};
+use bevy_hanabi::prelude::*;
+use ggrs::{Config, PlayerType, SessionBuilder};
+use serde::{Deserialize, Serialize};
 
-#[derive(Component)]
-struct Bullet;
+/// Distance from the origin to each arena wall. The player is clamped to
+/// this bound and bullets reflect off it instead of flying past.
+const ARENA_BOUND: f32 = 500.;
+
+/// A bullet bounces off the arena walls this many times before despawning.
+const MAX_BOUNCES: u8 = 5;
+
+#[derive(Component, Clone, Copy, Default)]
+struct Bullet {
+    bounces_remaining: u8,
+}
 
 #[derive(Component)]
 struct Collider;
 
-#[derive(Default)]
-struct CollisionEvent;
+#[derive(Component)]
+struct Wall;
 
 #[derive(Default)]
 struct Player(SpriteBundle);
 
-#[derive(Component)]
+#[derive(Component, Clone, Copy, Default)]
 struct Velocity(Vec2);
 
 #[derive(Component)]
 struct StartText;
 
-#[derive(Clone, Eq, PartialEq, Debug, Hash)]
+#[derive(States, Clone, Eq, PartialEq, Debug, Hash, Default)]
 enum AppState {
+    #[default]
     Start,
     Playing,
 }
 
+/// Fixed simulation rate the rollback schedule advances at, independent of
+/// render frame time, so both peers of a session step the exact same `dt`.
+const FPS: usize = 60;
+const DT: f32 = 1. / FPS as f32;
+
+const INPUT_UP: u8 = 1 << 0;
+const INPUT_DOWN: u8 = 1 << 1;
+const INPUT_LEFT: u8 = 1 << 2;
+const INPUT_RIGHT: u8 = 1 << 3;
+
+/// Per-frame local input, packed into a bitfield so it round-trips through
+/// GGRS's input serialization and compares cheaply during rollback.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug, Default)]
+struct BoxInput {
+    buttons: u8,
+}
+
+#[derive(Debug)]
+struct GgrsConfig;
+
+impl Config for GgrsConfig {
+    type Input = BoxInput;
+    type State = u8;
+    type Address = String;
+}
+
+/// Handle to the shared impact-burst particle effect, registered once in
+/// `setup` and spawned from at every bullet despawn / player hit.
+struct ImpactEffect(Handle<EffectAsset>);
+
+/// Sound asset handles loaded once in `setup`.
+struct AudioAssets {
+    hit: Handle<AudioSource>,
+    whoosh: Handle<AudioSource>,
+    music: Handle<AudioSource>,
+}
+
+/// The playing background music's sink, so it can be stopped when leaving
+/// `AppState::Playing`. Absent until the first run starts.
+struct MusicController(Handle<AudioSink>);
+
+/// Non-deterministic bookkeeping: entity handles and run-end UI state.
+/// Deliberately *not* rollback-tracked — raw `Entity` ids aren't meaningful
+/// to snapshot/restore across a resimulation, so the state that actually
+/// needs to roll back lives on the `SimState` component instead.
 #[derive(Default)]
 struct Game {
-    player: Option<Entity>,
+    /// One entity per GGRS player handle, indexed the same way
+    /// `PlayerInputs<GgrsConfig>` is. Both dodge the same bullet storm.
+    players: [Option<Entity>; 2],
+    /// Score of the run that just ended, so the start screen can show and
+    /// highlight it. Zero before the first run.
+    last_score: u128,
+    /// Whether `last_score` beat the leaderboard, captured before
+    /// `scores.record()` inserts it — `Scores::is_new_best` alone can't tell
+    /// a new best from a tie with the best once the score is already in the
+    /// table.
+    last_score_was_new_best: bool,
+}
+
+/// Deterministic simulation state, advanced only by systems in
+/// `GGRSSchedule` and rolled back/resimulated along with `Transform` and the
+/// other rollback components. Plain data only, no `Entity`/`Timer` fields:
+/// both are unsound to snapshot-and-restore (an entity id isn't portable
+/// across resimulation, and `Timer` carries wall-clock-flavored state we'd
+/// rather derive from `ticks` instead).
+#[derive(Component, Clone, Default)]
+struct SimState {
     score: u128,
-    timer: Timer,
+    /// Simulation ticks elapsed this run. `score` is derived from this count
+    /// rather than accumulated frame-by-frame, so per-tick rounding can't
+    /// drift the "milliseconds survived" away from wall-clock time.
+    ticks: u64,
+    /// Ticks since the last bullet wave, compared against the difficulty's
+    /// spawn interval (in ticks) instead of a `bevy::time::Timer`.
+    ticks_since_spawn: u32,
+    /// xorshift64 state, advanced once per bullet spawned. Seeded identically
+    /// on both peers at session start so bullet spawns stay in lockstep.
+    rng_state: u64,
+    /// Points to burst particles at this frame: bullet despawns and player
+    /// hits land here instead of on `bevy::Events`. `GGRSSchedule` can
+    /// resimulate the same real frame several times under synctest, and
+    /// events aren't rollback-tracked, so an `EventWriter` in that schedule
+    /// fires once per resim pass regardless of where the reader lives.
+    /// Clearing these at the top of `tick` (which always runs first) means
+    /// they hold exactly the last pass's output by the time the schedule is
+    /// done, however many passes produced it; FX/audio systems read them
+    /// once per real `Update` frame rather than draining them.
+    bullet_despawned_at: Vec<Vec3>,
+    player_hit_at: Vec<Vec3>,
+    bullet_spawned: bool,
+}
+
+/// Persistent top-10 leaderboard, serialized to a JSON file in the user's
+/// data directory and reloaded at startup.
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct Scores {
+    top: Vec<u128>,
+}
+
+impl Scores {
+    const MAX_ENTRIES: usize = 10;
+
+    fn record(&mut self, score: u128) {
+        self.top.push(score);
+        self.top.sort_unstable_by(|a, b| b.cmp(a));
+        self.top.truncate(Self::MAX_ENTRIES);
+    }
+
+    fn is_new_best(&self, score: u128) -> bool {
+        self.top.first().map_or(true, |&best| score > best)
+    }
+}
+
+fn scores_file_path() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("dodge_game")
+        .join("scores.json")
+}
+
+/// Loads the leaderboard from disk, falling back to an empty table if the
+/// file is missing or fails to parse.
+fn load_scores() -> Scores {
+    File::open(scores_file_path())
+        .ok()
+        .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+        .unwrap_or_default()
+}
+
+fn save_scores(scores: &Scores) {
+    let path = scores_file_path();
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Ok(file) = File::create(path) {
+        let _ = serde_json::to_writer_pretty(BufWriter::new(file), scores);
+    }
+}
+
+/// Tunable, data-driven challenge ramp. `bullet_spawn` interpolates between the
+/// `_start` and `_max`/`_min` values over `ramp_seconds` of survival time,
+/// scaling spawn rate, bullet speed, and burst size up as `SimState::score` grows.
+#[derive(Serialize, Deserialize, Clone)]
+struct Difficulty {
+    spawn_interval_start: f32,
+    spawn_interval_min: f32,
+    bullet_speed_start: f32,
+    bullet_speed_max: f32,
+    max_bullets_per_wave: u32,
+    ramp_seconds: f32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self {
+            spawn_interval_start: 0.2,
+            spawn_interval_min: 0.05,
+            bullet_speed_start: 1000.,
+            bullet_speed_max: 2500.,
+            max_bullets_per_wave: 5,
+            ramp_seconds: 60.,
+        }
+    }
+}
+
+impl Difficulty {
+    /// How far through the ramp `score` (milliseconds survived) is, in `[0, 1]`.
+    fn progress(&self, score: u128) -> f32 {
+        ((score as f32 / 1000.) / self.ramp_seconds.max(f32::EPSILON)).clamp(0., 1.)
+    }
+
+    fn spawn_interval(&self, progress: f32) -> f32 {
+        self.spawn_interval_start + (self.spawn_interval_min - self.spawn_interval_start) * progress
+    }
+
+    fn bullet_speed(&self, progress: f32) -> f32 {
+        self.bullet_speed_start + (self.bullet_speed_max - self.bullet_speed_start) * progress
+    }
+
+    fn bullets_per_wave(&self, progress: f32) -> u32 {
+        1 + ((self.max_bullets_per_wave - 1) as f32 * progress).round() as u32
+    }
+}
+
+fn difficulty_file_path() -> PathBuf {
+    PathBuf::from("difficulty.json")
+}
+
+/// Loads the difficulty ramp from disk, falling back to the built-in
+/// defaults if the file is missing or fails to parse.
+fn load_difficulty() -> Difficulty {
+    let mut difficulty: Difficulty = File::open(difficulty_file_path())
+        .ok()
+        .and_then(|file| serde_json::from_reader(BufReader::new(file)).ok())
+        .unwrap_or_default();
+    // A hand-edited file could set this to 0, which would underflow the
+    // `- 1` in `bullets_per_wave`; a wave of at least one bullet is the
+    // smallest meaningful difficulty anyway.
+    difficulty.max_bullets_per_wave = difficulty.max_bullets_per_wave.max(1);
+    difficulty
+}
+
+/// Advances a xorshift64 generator in place and returns the new state.
+/// Deterministic and allocation-free, so it is safe to call from rollback-
+/// replayed systems without diverging across peers.
+fn next_rand(state: &mut u64) -> u64 {
+    let mut x = *state;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    *state = x;
+    x
 }
 
 fn main() {
-    App::new()
-        .init_resource::<Game>()
+    let mut app = App::new();
+    GGRSPlugin::<GgrsConfig>::new()
+        .with_update_frequency(FPS)
+        .with_input_system(read_local_input)
+        .register_rollback_component::<Transform>()
+        .register_rollback_component::<Velocity>()
+        .register_rollback_component::<Bullet>()
+        .register_rollback_component::<SimState>()
+        .build(&mut app);
+
+    app.init_resource::<Game>()
+        .insert_resource(create_synctest_session())
+        .insert_resource(load_scores())
+        .insert_resource(load_difficulty())
         .add_plugins(DefaultPlugins)
-        .add_state(AppState::Start)
+        .add_plugin(HanabiPlugin)
+        .add_state::<AppState>()
         .add_startup_system(setup)
-        .add_system_set(SystemSet::on_enter(AppState::Start).with_system(setup_start))
-        .add_system_set(SystemSet::on_update(AppState::Start).with_system(start))
-        .add_system_set(SystemSet::on_enter(AppState::Playing).with_system(clean))
-        .add_system_set(
-            SystemSet::on_update(AppState::Playing)
-                .with_system(tick)
-                .with_system(score_update.after(tick))
-                .with_system(bullet_spawn.after(tick))
-                .with_system(bullet_movements.after(tick))
-                .with_system(player_movements.after(tick))
-                .with_system(check_for_collisions.after(tick)),
+        .add_systems((setup_start, stop_music).in_schedule(OnEnter(AppState::Start)))
+        .add_system(start.in_set(OnUpdate(AppState::Start)))
+        .add_system(clean.in_schedule(OnEnter(AppState::Playing)))
+        .add_systems(
+            (score_update, handle_player_hit, spawn_impact_particles, play_gameplay_sounds)
+                .in_set(OnUpdate(AppState::Playing)),
         )
+        .add_systems(
+            GGRSSchedule,
+            (
+                tick,
+                bullet_spawn.after(tick),
+                bullet_movements.after(tick),
+                player_movements.after(tick),
+                check_for_collisions.after(tick),
+            )
+                .distributive_run_if(in_state(AppState::Playing)),
+        )
+        .add_system(despawn_finished_bursts)
         .add_system(bevy::window::close_on_esc)
         .run();
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game: ResMut<Game>) {
-    commands.spawn_bundle(Camera2dBundle::default());
+/// Builds the local-vs-local synctest session used until real matchmaking is
+/// wired up: both "peers" run on this machine so desyncs between the two
+/// simulated players surface immediately as a checksum mismatch.
+fn create_synctest_session() -> bevy_ggrs::Session<GgrsConfig> {
+    let session = SessionBuilder::<GgrsConfig>::new()
+        .with_num_players(2)
+        .add_player(PlayerType::Local, 0)
+        .unwrap()
+        .add_player(PlayerType::Local, 1)
+        .unwrap()
+        .start_synctest_session()
+        .unwrap();
+    bevy_ggrs::Session::SyncTestSession(session)
+}
+
+/// Reads this machine's keyboard and packs it into the bitfield GGRS will
+/// serialize, send to the remote peer, and replay during rollback.
+fn read_local_input(_handle: In<ggrs::PlayerHandle>, keyboard_input: Res<Input<KeyCode>>) -> BoxInput {
+    let mut buttons = 0u8;
+    if keyboard_input.pressed(KeyCode::P) {
+        buttons |= INPUT_UP;
+    }
+    if keyboard_input.pressed(KeyCode::I) {
+        buttons |= INPUT_DOWN;
+    }
+    if keyboard_input.pressed(KeyCode::U) {
+        buttons |= INPUT_LEFT;
+    }
+    if keyboard_input.pressed(KeyCode::E) {
+        buttons |= INPUT_RIGHT;
+    }
+    BoxInput { buttons }
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut game: ResMut<Game>,
+    mut rip: ResMut<RollbackIdProvider>,
+    mut effects: ResMut<Assets<EffectAsset>>,
+) {
+    commands.spawn(Camera2dBundle::default());
+
+    commands.insert_resource(AudioAssets {
+        hit: asset_server.load("hit.ogg"),
+        whoosh: asset_server.load("whoosh.ogg"),
+        music: asset_server.load("music.ogg"),
+    });
+
+    let mut color_gradient = Gradient::new();
+    color_gradient.add_key(0.0, Vec4::new(0.9, 0.9, 0.2, 1.0));
+    color_gradient.add_key(1.0, Vec4::new(0.9, 0.9, 0.2, 0.0));
+
+    let mut size_gradient = Gradient::new();
+    size_gradient.add_key(0.0, Vec2::splat(4.0));
+    size_gradient.add_key(1.0, Vec2::splat(0.0));
+
+    let impact_effect = effects.add(
+        EffectAsset {
+            name: "impact_burst".to_string(),
+            capacity: 4096,
+            spawner: Spawner::once(30.0.into(), true),
+            ..default()
+        }
+        .init(PositionSphereModifier {
+            center: Vec3::ZERO,
+            radius: 1.0,
+            dimension: ShapeDimension::Surface,
+            speed: 150.0.into(),
+        })
+        .init(ParticleLifetimeModifier { lifetime: 0.4 })
+        .render(ColorOverLifetimeModifier {
+            gradient: color_gradient,
+        })
+        .render(SizeOverLifetimeModifier {
+            gradient: size_gradient,
+        }),
+    );
+    commands.insert_resource(ImpactEffect(impact_effect));
 
     // Backdrop
-    commands.spawn_bundle(SpriteBundle {
+    commands.spawn(SpriteBundle {
         transform: Transform {
             scale: Vec3::new(1015., 1015., 0.1),
             ..default()
@@ -71,7 +393,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game: ResMu
         },
         ..default()
     });
-    commands.spawn_bundle(SpriteBundle {
+    commands.spawn(SpriteBundle {
         transform: Transform {
             scale: Vec3::new(1010., 1010., 0.2),
             ..default()
@@ -83,29 +405,79 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game: ResMu
         ..default()
     });
 
-    game.player = Some(
+    const WALL_THICKNESS: f32 = 10.;
+    const WALL_SPAN: f32 = ARENA_BOUND * 2. + WALL_THICKNESS;
+    for (x, y, scale) in [
+        (-ARENA_BOUND, 0., Vec3::new(WALL_THICKNESS, WALL_SPAN, 1.)),
+        (ARENA_BOUND, 0., Vec3::new(WALL_THICKNESS, WALL_SPAN, 1.)),
+        (0., -ARENA_BOUND, Vec3::new(WALL_SPAN, WALL_THICKNESS, 1.)),
+        (0., ARENA_BOUND, Vec3::new(WALL_SPAN, WALL_THICKNESS, 1.)),
+    ] {
         commands
-            .spawn()
-            .insert_bundle(SpriteBundle {
+            .spawn(SpriteBundle {
                 transform: Transform {
-                    translation: Vec3::new(0., 0., 0.3),
-                    scale: Vec3::new(10., 10., 10.),
+                    translation: Vec3::new(x, y, 0.4),
+                    scale,
                     ..default()
                 },
                 sprite: Sprite {
-                    color: Color::rgb(0.9, 0., 0.),
+                    color: Color::GRAY,
                     ..default()
                 },
                 ..default()
             })
             .insert(Collider)
-            .id(),
-    );
+            .insert(Wall);
+    }
+
+    let player_one = commands
+        .spawn(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(-50., 0., 0.3),
+                scale: Vec3::new(10., 10., 10.),
+                ..default()
+            },
+            sprite: Sprite {
+                color: Color::rgb(0.9, 0., 0.),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Collider)
+        .insert(Rollback::new(rip.next_id()))
+        .id();
+
+    let player_two = commands
+        .spawn(SpriteBundle {
+            transform: Transform {
+                translation: Vec3::new(50., 0., 0.3),
+                scale: Vec3::new(10., 10., 10.),
+                ..default()
+            },
+            sprite: Sprite {
+                color: Color::rgb(0., 0.3, 0.9),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(Collider)
+        .insert(Rollback::new(rip.next_id()))
+        .id();
+
+    game.players = [Some(player_one), Some(player_two)];
 
-    game.timer = Timer::from_seconds(0.2, true);
+    commands
+        .spawn(SimState {
+            // Seeded once here with a fixed constant that every peer uses,
+            // so both xorshift streams agree; the seed is not exchanged
+            // through the session.
+            rng_state: 0x9E3779B97F4A7C15,
+            ..default()
+        })
+        .insert(Rollback::new(rip.next_id()));
 
     commands
-        .spawn_bundle(NodeBundle {
+        .spawn(NodeBundle {
             style: Style {
                 position_type: PositionType::Absolute,
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
@@ -115,22 +487,22 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game: ResMu
                 justify_content: JustifyContent::FlexEnd,
                 ..default()
             },
-            color: UiColor(Color::NONE),
+            background_color: BackgroundColor(Color::NONE),
             ..default()
         })
         .with_children(|parent| {
             parent
-                .spawn_bundle(NodeBundle {
+                .spawn(NodeBundle {
                     style: Style {
                         border: UiRect::all(Val::Px(2.0)),
                         padding: UiRect::all(Val::Px(10.)),
                         ..default()
                     },
-                    color: UiColor(Color::GRAY),
+                    background_color: BackgroundColor(Color::GRAY),
                     ..default()
                 })
                 .with_children(|parent| {
-                    parent.spawn_bundle(
+                    parent.spawn(
                         TextBundle::from_section(
                             // Accepts a `String` or any type that converts into a `String`, such as `&str`
                             "Score: 0",
@@ -140,15 +512,20 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>, mut game: ResMu
                                 color: Color::BLACK,
                             },
                         )
-                        .with_text_alignment(TextAlignment::TOP_CENTER), // Set the alignment of the Text
+                        .with_text_alignment(TextAlignment::Center), // Set the alignment of the Text
                     );
                 });
         });
 }
 
-fn setup_start(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_start(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game: Res<Game>,
+    scores: Res<Scores>,
+) {
     commands
-        .spawn_bundle(NodeBundle {
+        .spawn(NodeBundle {
             style: Style {
                 position_type: PositionType::Absolute,
                 size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
@@ -158,12 +535,12 @@ fn setup_start(mut commands: Commands, asset_server: Res<AssetServer>) {
                 justify_content: JustifyContent::Center,
                 ..default()
             },
-            color: UiColor(Color::NONE),
+            background_color: BackgroundColor(Color::NONE),
             ..default()
         })
         .with_children(|parent| {
             parent
-                .spawn_bundle(
+                .spawn(
                     TextBundle::from_section(
                         // Accepts a `String` or any type that converts into a `String`, such as `&str`
                         "Press Space to Start",
@@ -173,23 +550,69 @@ fn setup_start(mut commands: Commands, asset_server: Res<AssetServer>) {
                             color: Color::WHITE,
                         },
                     )
-                    .with_text_alignment(TextAlignment::TOP_CENTER), // Set the alignment of the Text
+                    .with_text_alignment(TextAlignment::Center), // Set the alignment of the Text
                 )
                 .insert(StartText);
+
+            if game.last_score > 0 {
+                let (text, color) = if game.last_score_was_new_best {
+                    (format!("New best: {}!", game.last_score), Color::GOLD)
+                } else {
+                    (format!("Score: {}", game.last_score), Color::WHITE)
+                };
+                parent
+                    .spawn(
+                        TextBundle::from_section(
+                            text,
+                            TextStyle {
+                                font: asset_server.load("DejaVuSans.ttf"),
+                                font_size: 30.0,
+                                color,
+                            },
+                        )
+                        .with_text_alignment(TextAlignment::Center),
+                    )
+                    .insert(StartText);
+            }
+
+            if !scores.top.is_empty() {
+                let leaderboard = scores
+                    .top
+                    .iter()
+                    .enumerate()
+                    .map(|(rank, score)| format!("{}. {}", rank + 1, score))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                parent
+                    .spawn(
+                        TextBundle::from_section(
+                            format!("High Scores\n{}", leaderboard),
+                            TextStyle {
+                                font: asset_server.load("DejaVuSans.ttf"),
+                                font_size: 24.0,
+                                color: Color::WHITE,
+                            },
+                        )
+                        .with_text_alignment(TextAlignment::Center),
+                    )
+                    .insert(StartText);
+            }
         });
 }
 
-fn start(mut state: ResMut<State<AppState>>, keyboard_input: Res<Input<KeyCode>>) {
+fn start(mut next_state: ResMut<NextState<AppState>>, keyboard_input: Res<Input<KeyCode>>) {
     if keyboard_input.pressed(KeyCode::Space) {
-        state.set(AppState::Playing).unwrap();
+        next_state.set(AppState::Playing);
     }
 }
 
 fn clean(
     mut commands: Commands,
-    mut game: ResMut<Game>,
     bullets: Query<Entity, With<Bullet>>,
     start_texts: Query<Entity, With<StartText>>,
+    mut sim_state: Query<&mut SimState>,
+    audio: Res<Audio>,
+    audio_assets: Res<AudioAssets>,
 ) {
     for entity in &bullets {
         commands.entity(entity).despawn_recursive();
@@ -197,35 +620,106 @@ fn clean(
     for entity in &start_texts {
         commands.entity(entity).despawn_recursive();
     }
-    game.score = 0;
+    let rng_state = sim_state.single().rng_state;
+    *sim_state.single_mut() = SimState {
+        rng_state,
+        ..default()
+    };
+
+    let sink = audio.play_with_settings(audio_assets.music.clone(), PlaybackSettings::LOOP);
+    commands.insert_resource(MusicController(sink));
+}
+
+/// Stops the background music when returning to the start screen. A no-op
+/// the very first time `AppState::Start` is entered, before any run has
+/// started the music.
+fn stop_music(music: Option<Res<MusicController>>, audio_sinks: Res<Assets<AudioSink>>) {
+    if let Some(music) = music {
+        if let Some(sink) = audio_sinks.get(&music.0) {
+            sink.stop();
+        }
+    }
 }
 
-fn tick(time: Res<Time>, mut game: ResMut<Game>) {
-    game.timer.tick(time.delta()).just_finished();
-    game.score += time.delta().as_millis();
+fn tick(mut sim_state: Query<&mut SimState>) {
+    let mut sim = sim_state.single_mut();
+    // Always the first system in `GGRSSchedule`, so this is where the
+    // per-frame FX buffers get cleared before any writer can refill them —
+    // see the comment on `SimState::bullet_despawned_at`.
+    sim.bullet_despawned_at.clear();
+    sim.player_hit_at.clear();
+    sim.bullet_spawned = false;
+    // Derived from the exact tick count rather than `+= DT * 1000.` each
+    // call: `DT` (1/60 s) is 16.67 ms, and truncating that every tick loses
+    // ~4% of elapsed time over a run instead of rounding it away once here.
+    sim.ticks += 1;
+    sim.score = (sim.ticks as u128 * 1000) / FPS as u128;
+    sim.ticks_since_spawn += 1;
 }
 
-fn score_update(game: Res<Game>, mut query: Query<&mut Text>) {
+fn score_update(sim_state: Query<&SimState>, mut query: Query<&mut Text>) {
+    let score = sim_state.single().score;
     for mut text in &mut query {
-        text.sections[0].value = format!("Score: {}", game.score.to_string());
+        text.sections[0].value = format!("Score: {}", score.to_string());
     }
 }
 
 fn bullet_spawn(
     mut commands: Commands,
-    game: ResMut<Game>,
+    game: Res<Game>,
+    mut sim_state: Query<&mut SimState>,
+    difficulty: Res<Difficulty>,
+    mut rip: ResMut<RollbackIdProvider>,
     asset_server: Res<AssetServer>,
     mut transforms: Query<&mut Transform>,
 ) {
-    if game.timer.just_finished() {
-        let player_transform = transforms.get_mut(game.player.unwrap()).unwrap();
-        let rand_angle = rand::thread_rng().gen_range(0..(PI * 2000.) as u32) as f32 / 1000.;
+    let mut sim = sim_state.single_mut();
+
+    let progress = difficulty.progress(sim.score);
+    // Spawn cadence is tracked in whole ticks rather than a `Timer`, so it
+    // rolls back deterministically along with the rest of `SimState`.
+    let interval_ticks = ((difficulty.spawn_interval(progress) * FPS as f32).round() as u32).max(1);
+    if sim.ticks_since_spawn < interval_ticks {
+        return;
+    }
+    sim.ticks_since_spawn = 0;
+
+    let bullet_speed = difficulty.bullet_speed(progress);
+    let wave_size = difficulty.bullets_per_wave(progress);
+    // Both players dodge the same storm, so each bullet just homes on
+    // whichever of them is nearer its spawn point.
+    let player_translations: Vec<Vec3> = game
+        .players
+        .iter()
+        .filter_map(|&player| player)
+        .map(|player| transforms.get_mut(player).unwrap().translation)
+        .collect();
+    sim.bullet_spawned = true;
+
+    for _ in 0..wave_size {
+        let rand_u64 = next_rand(&mut sim.rng_state);
+        let rand_angle = (rand_u64 % (PI * 2000.) as u64) as f32 / 1000.;
         let rand_quad = Quat::from_rotation_z(rand_angle);
-        let spawn_location = rand_quad * Vec3::new(1., 0., 0.).normalize() * 700.;
-        let diff = player_transform.translation - spawn_location;
+        // Radius is the arena bound itself, not beyond it: a rotated unit
+        // vector scaled by `ARENA_BOUND` always lands with both components
+        // inside [-ARENA_BOUND, ARENA_BOUND], so a freshly spawned bullet
+        // never starts outside the walls and burning bounces before it's
+        // even travelled inward.
+        let spawn_location = rand_quad * Vec3::new(1., 0., 0.).normalize() * ARENA_BOUND;
+        let target = player_translations
+            .iter()
+            .min_by(|a, b| {
+                (**a - spawn_location)
+                    .length_squared()
+                    .partial_cmp(&(**b - spawn_location).length_squared())
+                    .unwrap()
+            })
+            .copied()
+            .unwrap_or(Vec3::ZERO);
+        let diff = target - spawn_location;
         let angle = diff.y.atan2(diff.x);
         commands
-            .spawn_bundle(SpriteBundle {
+            .spawn(SpriteBundle {
                 texture: asset_server.load("bullet.png"),
                 transform: Transform {
                     translation: spawn_location,
@@ -239,148 +733,302 @@ fn bullet_spawn(
                 },
                 ..default()
             })
-            .insert(Bullet)
+            .insert(Bullet {
+                bounces_remaining: MAX_BOUNCES,
+            })
             .insert(Velocity(
-                Vec2::new(diff.x, diff.y).normalize() * Vec2::new(1000., 1000.),
+                Vec2::new(diff.x, diff.y).normalize() * Vec2::new(bullet_speed, bullet_speed),
             ))
-            .insert(Collider);
+            .insert(Collider)
+            .insert(Rollback::new(rip.next_id()));
     }
 }
 
 fn bullet_movements(
-    time: Res<Time>,
     mut commands: Commands,
-    mut bullet_velocities: Query<(Entity, &Velocity, &mut Transform), With<Bullet>>,
+    mut sim_state: Query<&mut SimState>,
+    mut bullets: Query<(Entity, &mut Bullet, &mut Velocity, &mut Transform)>,
 ) {
-    for (entity, velocity, mut transform) in &mut bullet_velocities {
-        transform.translation.x += velocity.0.x * time.delta_seconds();
-        transform.translation.y += velocity.0.y * time.delta_seconds();
+    let mut sim = sim_state.single_mut();
+    for (entity, mut bullet, mut velocity, mut transform) in &mut bullets {
+        transform.translation.x += velocity.0.x * DT;
+        transform.translation.y += velocity.0.y * DT;
 
-        const LIMIT: f32 = 1000.;
-        if transform.translation.x > LIMIT || transform.translation.x < -LIMIT {
-            commands.entity(entity).despawn_recursive();
+        let mut bounced = false;
+        if transform.translation.x > ARENA_BOUND || transform.translation.x < -ARENA_BOUND {
+            velocity.0.x = -velocity.0.x;
+            transform.translation.x = transform.translation.x.clamp(-ARENA_BOUND, ARENA_BOUND);
+            bounced = true;
         }
-        if transform.translation.y > LIMIT || transform.translation.y < -LIMIT {
-            commands.entity(entity).despawn_recursive();
+        if transform.translation.y > ARENA_BOUND || transform.translation.y < -ARENA_BOUND {
+            velocity.0.y = -velocity.0.y;
+            transform.translation.y = transform.translation.y.clamp(-ARENA_BOUND, ARENA_BOUND);
+            bounced = true;
+        }
+
+        if bounced {
+            if bullet.bounces_remaining == 0 {
+                commands.entity(entity).despawn_recursive();
+                sim.bullet_despawned_at.push(transform.translation);
+            } else {
+                bullet.bounces_remaining -= 1;
+            }
         }
     }
 }
 
 fn player_movements(
-    time: Res<Time>,
-    keyboard_input: Res<Input<KeyCode>>,
+    inputs: Res<PlayerInputs<GgrsConfig>>,
     game: ResMut<Game>,
     mut transforms: Query<&mut Transform>,
 ) {
     const PLAYER_SPEED: f32 = 250.;
-    let mut player_transform = transforms.get_mut(game.player.unwrap()).unwrap();
-    let mut direction = Vec3::new(0., 0., 0.);
-    if keyboard_input.pressed(KeyCode::E) {
-        direction.x += 1.;
-    };
-    if keyboard_input.pressed(KeyCode::U) {
-        direction.x -= 1.;
-    };
-    if keyboard_input.pressed(KeyCode::P) {
-        direction.y += 1.;
-    };
-    if keyboard_input.pressed(KeyCode::I) {
-        direction.y -= 1.;
-    };
-    if direction.length() > 0. {
-        player_transform.translation += direction.normalize() * PLAYER_SPEED * time.delta_seconds();
-    }
+    for (handle, player) in game.players.iter().enumerate() {
+        let player = match player {
+            Some(player) => *player,
+            None => continue,
+        };
+        let mut player_transform = transforms.get_mut(player).unwrap();
+        let (input, _) = inputs[handle];
+        let mut direction = Vec3::new(0., 0., 0.);
+        if input.buttons & INPUT_RIGHT != 0 {
+            direction.x += 1.;
+        };
+        if input.buttons & INPUT_LEFT != 0 {
+            direction.x -= 1.;
+        };
+        if input.buttons & INPUT_UP != 0 {
+            direction.y += 1.;
+        };
+        if input.buttons & INPUT_DOWN != 0 {
+            direction.y -= 1.;
+        };
+        if direction.length() > 0. {
+            player_transform.translation += direction.normalize() * PLAYER_SPEED * DT;
+        }
 
-    player_transform.translation.x = player_transform.translation.x.clamp(-500., 500.);
-    player_transform.translation.y = player_transform.translation.y.clamp(-500., 500.);
+        player_transform.translation.x = player_transform.translation.x.clamp(-ARENA_BOUND, ARENA_BOUND);
+        player_transform.translation.y = player_transform.translation.y.clamp(-ARENA_BOUND, ARENA_BOUND);
+    }
 }
 
 fn check_for_collisions(
-    time: Res<Time>,
-    mut state: ResMut<State<AppState>>,
+    mut sim_state: Query<&mut SimState>,
     game: ResMut<Game>,
     assets: Res<Assets<Image>>,
     transforms: Query<&Transform>,
     collider_query: Query<(Entity, &Handle<Image>, &Transform, &Velocity, &Collider), With<Bullet>>,
 ) {
-    let player_transform = transforms.get(game.player.unwrap()).unwrap();
-
-    for (_collider_entity, texture_handle, transform, velocity, _collider) in &collider_query {
-        if let Some(texture) = assets.get(texture_handle) {
-            let size = texture.size() * transform.scale.truncate();
-            if collide_with_rotation_multistep(
-                time.clone(),
-                player_transform.translation.truncate(),
-                transform.translation.truncate(),
-                size,
-                transform.rotation,
-                velocity.0,
-                5,
-            ) {
-                println!("Score was: {}", game.score);
-                state.overwrite_set(AppState::Start).unwrap();
+    let mut sim = sim_state.single_mut();
+    for player in game.players.iter().filter_map(|&player| player) {
+        let player_transform = transforms.get(player).unwrap();
+
+        for (_collider_entity, texture_handle, transform, velocity, _collider) in &collider_query {
+            if let Some(texture) = assets.get(texture_handle) {
+                let size = texture.size() * transform.scale.truncate();
+                if collide_swept(
+                    player_transform.translation.truncate(),
+                    transform.translation.truncate(),
+                    size,
+                    transform.rotation,
+                    velocity.0,
+                    DT,
+                ) {
+                    sim.player_hit_at.push(player_transform.translation);
+                }
             }
         }
     }
 }
 
+fn handle_player_hit(
+    mut next_state: ResMut<NextState<AppState>>,
+    mut game: ResMut<Game>,
+    sim_state: Query<&SimState>,
+    mut scores: ResMut<Scores>,
+) {
+    // A wave can land several bullets on the player in the same frame, so
+    // `player_hit_at` can hold more than one point for a single death. Only
+    // react once per death; reacting to each point would duplicate the
+    // leaderboard entry and re-transition out of `Start`.
+    if !sim_state.single().player_hit_at.is_empty() {
+        let score = sim_state.single().score;
+        println!("Score was: {}", score);
+        game.last_score = score;
+        // Must be checked before `record` inserts the score, or a tie with
+        // the existing best would also read back as "new".
+        game.last_score_was_new_best = scores.is_new_best(score);
+        scores.record(score);
+        save_scores(&scores);
+        next_state.set(AppState::Start);
+    }
+}
+
+/// Spawns a one-shot impact burst at every bullet despawn / player hit this
+/// frame. Kept out of the rollback schedule: the bursts are pure visual
+/// flourish and re-spawning them on a rollback replay would double them up.
+fn spawn_impact_particles(mut commands: Commands, effect: Res<ImpactEffect>, sim_state: Query<&SimState>) {
+    let sim = sim_state.single();
+    for &position in sim.bullet_despawned_at.iter().chain(sim.player_hit_at.iter()) {
+        spawn_burst(&mut commands, &effect, position);
+    }
+}
+
+/// Lifetime of a burst entity, matching the particle system's own
+/// `ParticleLifetimeModifier` so the entity is despawned only once its
+/// particles have finished rendering.
+const IMPACT_BURST_LIFETIME: f32 = 0.4;
+
+/// Marks a `ParticleEffectBundle` spawned by `spawn_burst` for cleanup once
+/// its particles have finished; without this the bundle entity itself is
+/// never despawned and bursts leak for the life of the process.
+#[derive(Component)]
+struct ImpactBurst {
+    timer: Timer,
+}
+
+fn spawn_burst(commands: &mut Commands, effect: &ImpactEffect, position: Vec3) {
+    commands
+        .spawn(ParticleEffectBundle {
+            effect: ParticleEffect::new(effect.0.clone()),
+            transform: Transform::from_translation(position),
+            ..default()
+        })
+        .insert(ImpactBurst {
+            timer: Timer::from_seconds(IMPACT_BURST_LIFETIME, TimerMode::Once),
+        });
+}
+
+fn despawn_finished_bursts(mut commands: Commands, time: Res<Time>, mut bursts: Query<(Entity, &mut ImpactBurst)>) {
+    for (entity, mut burst) in &mut bursts {
+        burst.timer.tick(time.delta());
+        if burst.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+/// Plays one-shot sound effects off the same per-frame buffers the particle
+/// subsystem reacts to, keeping playback decoupled from the simulation
+/// systems.
+fn play_gameplay_sounds(audio: Res<Audio>, audio_assets: Res<AudioAssets>, sim_state: Query<&SimState>) {
+    let sim = sim_state.single();
+    if sim.bullet_spawned {
+        audio.play(audio_assets.whoosh.clone());
+    }
+    for _ in &sim.player_hit_at {
+        audio.play(audio_assets.hit.clone());
+    }
+}
+
 // ============== COLLISION DETECTION ==============
 
-fn collide_with_rotation_multistep(
-    time: Time,
+/// Exact continuous (swept) point-vs-rotated-box test over one simulation
+/// step. Unlike sampling discrete positions along the box's path, this can't
+/// miss a crossing no matter how fast `rectangle_velocity` is.
+///
+/// Works in the box's local frame: the point's relative position and
+/// relative velocity (the box's motion, negated) are rotated by the inverse
+/// of `rectangle_rotation`, turning the problem into a point moving along a
+/// straight line against an axis-aligned box of half-extents
+/// `rectangle_size / 2`. A standard 2D slab test then finds the `t` interval
+/// (within `[0, 1]`) where the point is inside the box on every axis at once.
+fn collide_swept(
     point: Vec2,
     rectangle_position: Vec2,
     rectangle_size: Vec2,
     rectangle_rotation: Quat,
     rectangle_velocity: Vec2,
-    steps: u16,
+    dt: f32,
 ) -> bool {
-    for i in 0..steps {
-        if collide_with_rotation(
-            point,
-            rectangle_position
-                - (i as f32 / steps as f32) * rectangle_velocity * time.clone().delta_seconds(),
-            rectangle_size,
-            rectangle_rotation,
-        ) {
-            return true;
+    let inverse_rotation = rectangle_rotation.inverse();
+    let relative_position = inverse_rotation
+        .mul_vec3((point - rectangle_position).extend(0.))
+        .truncate();
+    let relative_velocity = inverse_rotation
+        .mul_vec3((-rectangle_velocity * dt).extend(0.))
+        .truncate();
+    let half_extents = rectangle_size / 2.;
+
+    let mut t_enter = 0.;
+    let mut t_exit = 1.;
+
+    for axis in 0..2 {
+        let p = relative_position[axis];
+        let v = relative_velocity[axis];
+        let half = half_extents[axis];
+
+        if v.abs() < f32::EPSILON {
+            if p < -half || p > half {
+                return false;
+            }
+            continue;
+        }
+
+        let mut t0 = (-half - p) / v;
+        let mut t1 = (half - p) / v;
+        if t0 > t1 {
+            std::mem::swap(&mut t0, &mut t1);
+        }
+        t_enter = t_enter.max(t0);
+        t_exit = t_exit.min(t1);
+        if t_enter > t_exit {
+            return false;
         }
     }
-    false
-}
 
-fn collide_with_rotation(
-    point: Vec2,
-    rectangle_position: Vec2,
-    rectangle_size: Vec2,
-    rectangle_rotation: Quat,
-) -> bool {
-    let point_1 = Vec3::new(-rectangle_size.x / 2., rectangle_size.y / 2., 0.);
-    let point_2 = Vec3::new(-rectangle_size.x / 2., -rectangle_size.y / 2., 0.);
-    let point_3 = Vec3::new(rectangle_size.x / 2., rectangle_size.y / 2., 0.);
-    let point_4 = Vec3::new(rectangle_size.x / 2., -rectangle_size.y / 2., 0.);
-    is_point_inside_rectangle(
-        point,
-        rectangle_rotation.mul_vec3(point_1).truncate() + rectangle_position,
-        rectangle_rotation.mul_vec3(point_2).truncate() + rectangle_position,
-        rectangle_rotation.mul_vec3(point_3).truncate() + rectangle_position,
-        rectangle_rotation.mul_vec3(point_4).truncate() + rectangle_position,
-    )
+    t_enter <= t_exit
 }
 
-fn is_point_inside_rectangle(t: Vec2, p1: Vec2, p2: Vec2, p3: Vec2, p4: Vec2) -> bool {
-    is_point_inside_triangle(t, p1, p2, p3) || is_point_inside_triangle(t, p2, p4, p3)
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn is_point_inside_triangle(t: Vec2, p1: Vec2, p2: Vec2, p3: Vec2) -> bool {
-    let area_ref = area(p1, p2, p3);
+    #[test]
+    fn detects_a_bullet_that_crosses_the_player_within_one_step() {
+        assert!(collide_swept(
+            Vec2::ZERO,
+            Vec2::new(-5., 0.),
+            Vec2::splat(4.),
+            Quat::IDENTITY,
+            Vec2::new(600., 0.),
+            1. / 60.,
+        ));
+    }
 
-    let area_1 = area(p1, p2, t);
-    let area_2 = area(p1, t, p3);
-    let area_3 = area(t, p2, p3);
-    area_ref >= area_1 + area_2 + area_3
-}
+    #[test]
+    fn misses_a_bullet_that_passes_just_outside_the_box() {
+        assert!(!collide_swept(
+            Vec2::ZERO,
+            Vec2::new(-5., 3.),
+            Vec2::splat(4.),
+            Quat::IDENTITY,
+            Vec2::new(600., 0.),
+            1. / 60.,
+        ));
+    }
 
-fn area(p1: Vec2, p2: Vec2, p3: Vec2) -> f32 {
-    return ((p1.x * (p2.y - p3.y) + p2.x * (p3.y - p1.y) + p3.x * (p1.y - p2.y)) / 2.).abs();
+    #[test]
+    fn detects_a_stationary_box_already_containing_the_point() {
+        assert!(collide_swept(
+            Vec2::new(1., 1.),
+            Vec2::ZERO,
+            Vec2::splat(4.),
+            Quat::IDENTITY,
+            Vec2::ZERO,
+            1. / 60.,
+        ));
+    }
+
+    #[test]
+    fn misses_a_stationary_box_that_never_contained_the_point() {
+        assert!(!collide_swept(
+            Vec2::new(10., 10.),
+            Vec2::ZERO,
+            Vec2::splat(4.),
+            Quat::IDENTITY,
+            Vec2::ZERO,
+            1. / 60.,
+        ));
+    }
 }